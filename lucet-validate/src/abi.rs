@@ -0,0 +1,101 @@
+//! Lowering of witx interface functions to the core wasm signatures the
+//! WASI ABI expects a module to export.
+
+use crate::FuncSignature;
+use wasmparser::Type;
+
+/// The single core value a scalar witx type lowers to: `i32` for everything
+/// that fits in 32 bits (bools, enums, flags, small integers, pointers,
+/// handles), `i64` for 64-bit integers, and the matching float width for
+/// floats.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum AtomType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl AtomType {
+    fn to_wasm(self) -> Type {
+        match self {
+            AtomType::I32 => Type::I32,
+            AtomType::I64 => Type::I64,
+            AtomType::F32 => Type::F32,
+            AtomType::F64 => Type::F64,
+        }
+    }
+}
+
+/// Lower a single witx type reference to the core wasm value(s) it occupies
+/// in the WASI ABI. Most types lower to exactly one atom; `string` and
+/// `array` lower to a pointer/length pair.
+fn lower_type(tref: &witx::TypeRef) -> Vec<AtomType> {
+    match &*tref.type_() {
+        witx::Type::Builtin(witx::BuiltinType::String) => vec![AtomType::I32, AtomType::I32],
+        witx::Type::Builtin(b) => vec![lower_builtin(*b)],
+        witx::Type::Enum(e) => vec![lower_int_repr(e.repr)],
+        witx::Type::Int(i) => vec![lower_int_repr(i.repr)],
+        witx::Type::Flags(f) => vec![lower_int_repr(f.repr)],
+        witx::Type::Array(_) => vec![AtomType::I32, AtomType::I32],
+        witx::Type::Pointer(_) | witx::Type::ConstPointer(_) => vec![AtomType::I32],
+        witx::Type::Handle(_) => vec![AtomType::I32],
+        // Structs and unions are passed by reference; the ABI only ever
+        // sees the pointer to their in-memory representation.
+        witx::Type::Struct(_) | witx::Type::Union(_) => vec![AtomType::I32],
+    }
+}
+
+fn lower_builtin(b: witx::BuiltinType) -> AtomType {
+    use witx::BuiltinType::*;
+    match b {
+        U8 | U16 | U32 | S8 | S16 | S32 | Char8 | USize => AtomType::I32,
+        U64 | S64 => AtomType::I64,
+        F32 => AtomType::F32,
+        F64 => AtomType::F64,
+        String => unreachable!("string is lowered directly by lower_type"),
+    }
+}
+
+fn lower_int_repr(repr: witx::IntRepr) -> AtomType {
+    match repr {
+        witx::IntRepr::U8 | witx::IntRepr::U16 | witx::IntRepr::U32 => AtomType::I32,
+        witx::IntRepr::U64 => AtomType::I64,
+    }
+}
+
+/// Compute the core wasm export name and expected signature for a witx
+/// interface function, following the witx/WASI ABI lowering: each param
+/// lowers in place, the first result (if any) becomes the core return
+/// value when it lowers to a single atom, and a multi-atom first result
+/// and any further results become trailing `i32` out-param pointers
+/// appended to the parameter list.
+pub(crate) fn lower_func(
+    module: &witx::Module,
+    func: &witx::InterfaceFunc,
+) -> (String, FuncSignature) {
+    let mut params = Vec::new();
+    for param in &func.params {
+        params.extend(lower_type(&param.tref).into_iter().map(AtomType::to_wasm));
+    }
+
+    let mut results = func.results.iter();
+    let mut returns = Vec::new();
+    if let Some(first) = results.next() {
+        let atoms = lower_type(&first.tref);
+        match atoms.as_slice() {
+            // Only a genuinely scalar first result can be returned
+            // directly; a multi-atom result like `string`/`array` can't fit
+            // in a single core return value, so it becomes an out-param
+            // pointer just like any other non-first result.
+            [atom] => returns.push(atom.to_wasm()),
+            _ => params.push(Type::I32),
+        }
+    }
+    for _extra_result in results {
+        params.push(Type::I32);
+    }
+
+    let name = format!("{}::{}", module.name.as_str(), func.name.as_str());
+    (name, FuncSignature { params, returns })
+}
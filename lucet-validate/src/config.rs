@@ -0,0 +1,48 @@
+//! Toggles for the WebAssembly proposals `validate` accepts. The defaults
+//! match the behavior before this config existed, so existing callers that
+//! construct a default config see no change.
+
+/// Which WebAssembly proposals the core module is allowed to use.
+///
+/// These are independent of the witx ABI lowering: a module may use
+/// multi-value returns, reference-typed imports, bulk memory operations,
+/// and so on for reasons of its own, unrelated to the interface it
+/// implements, and `validate` needs to know whether to accept that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidationConfig {
+    pub multi_value: bool,
+    pub reference_types: bool,
+    pub bulk_memory: bool,
+    pub simd: bool,
+    pub multi_memory: bool,
+}
+
+impl Default for ValidationConfig {
+    // `wasmparser::validate(_, None)` validates against `WasmFeatures::default()`,
+    // so this has to mirror those defaults field-for-field rather than
+    // assuming they're all `false` — otherwise a default `ValidationConfig`
+    // would silently accept or reject modules differently than `None` did.
+    fn default() -> Self {
+        let features = wasmparser::WasmFeatures::default();
+        ValidationConfig {
+            multi_value: features.multi_value,
+            reference_types: features.reference_types,
+            bulk_memory: features.bulk_memory,
+            simd: features.simd,
+            multi_memory: features.multi_memory,
+        }
+    }
+}
+
+impl ValidationConfig {
+    pub(crate) fn wasmparser_features(&self) -> wasmparser::WasmFeatures {
+        wasmparser::WasmFeatures {
+            multi_value: self.multi_value,
+            reference_types: self.reference_types,
+            bulk_memory: self.bulk_memory,
+            simd: self.simd,
+            multi_memory: self.multi_memory,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::FuncSignature;
+use failure::Fail;
+use std::fmt;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "WebAssembly validation error at offset {}: {}", _1, 0)]
+    WasmValidation(&'static str, usize),
+    #[fail(display = "Unsupported: {}", _0)]
+    Unsupported(String),
+    #[fail(display = "Missing export: interface function \"{}\" is not exported", _0)]
+    MissingExport(String),
+    #[fail(
+        display = "Missing export: interface function \"{}\" is not exported (the module defines a function named \"{}\" but does not export it)",
+        _0, _1
+    )]
+    MissingExportButNamed(String, String),
+    #[fail(display = "Missing required \"memory\" export")]
+    MissingMemory,
+    #[fail(display = "export \"{}\": expected {}, found {}", func, expected, found)]
+    InterfaceMismatch {
+        func: String,
+        expected: FuncSignature,
+        found: FuncSignature,
+    },
+}
+
+impl From<wasmparser::BinaryReaderError> for Error {
+    fn from(e: wasmparser::BinaryReaderError) -> Error {
+        Error::WasmValidation(e.message, e.offset)
+    }
+}
+
+/// Every interface violation found in one pass over a module, so a caller
+/// sees all of them at once instead of stopping at the first `?`.
+#[derive(Debug)]
+pub struct Errors(pub Vec<Error>);
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl Fail for Errors {}
@@ -1,25 +1,19 @@
+mod abi;
+mod config;
+mod errors;
+
+use abi::lower_func;
+pub use config::ValidationConfig;
 use cranelift_entity::{entity_impl, PrimaryMap};
-use failure::Fail;
+pub use errors::{Error, Errors};
 use std::collections::HashMap;
+use std::fmt;
 use wasmparser::{
-    self, ExternalKind, FuncType, ImportSectionEntryType, ModuleReader, SectionContent, Type,
+    self, ExternalKind, FuncType, ImportSectionEntryType, ModuleReader, Name, NameSectionReader,
+    SectionContent, Type,
 };
 use witx;
 
-#[derive(Debug, Fail)]
-pub enum Error {
-    #[fail(display = "WebAssembly validation error at offset {}: {}", _1, 0)]
-    WasmValidation(&'static str, usize),
-    #[fail(display = "Unsupported: {}", _0)]
-    Unsupported(String),
-}
-
-impl From<wasmparser::BinaryReaderError> for Error {
-    fn from(e: wasmparser::BinaryReaderError) -> Error {
-        Error::WasmValidation(e.message, e.offset)
-    }
-}
-
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
 struct TypeIndex(u32);
 entity_impl!(TypeIndex);
@@ -28,10 +22,60 @@ entity_impl!(TypeIndex);
 struct FuncIndex(u32);
 entity_impl!(FuncIndex);
 
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+struct MemoryIndex(u32);
+entity_impl!(MemoryIndex);
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+struct TableIndex(u32);
+entity_impl!(TableIndex);
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+struct GlobalIndex(u32);
+entity_impl!(GlobalIndex);
+
 #[derive(Clone, Debug, PartialEq, Eq)]
-struct FuncSignature {
-    params: Vec<Type>,
-    returns: Vec<Type>,
+pub struct FuncSignature {
+    pub params: Vec<Type>,
+    pub returns: Vec<Type>,
+}
+
+fn display_valtype(f: &mut fmt::Formatter, ty: Type) -> fmt::Result {
+    write!(
+        f,
+        "{}",
+        match ty {
+            Type::I32 => "i32",
+            Type::I64 => "i64",
+            Type::F32 => "f32",
+            Type::F64 => "f64",
+            _ => "?",
+        }
+    )
+}
+
+impl fmt::Display for FuncSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        for (i, p) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            display_valtype(f, *p)?;
+        }
+        write!(f, ")->")?;
+        if self.returns.is_empty() {
+            write!(f, "()")?;
+        } else {
+            for (i, r) in self.returns.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ",")?;
+                }
+                display_valtype(f, *r)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -44,16 +88,113 @@ struct Func {
 struct ModuleType {
     types: PrimaryMap<TypeIndex, FuncSignature>,
     funcs: PrimaryMap<FuncIndex, Func>,
+    memories: PrimaryMap<MemoryIndex, wasmparser::MemoryType>,
+    tables: PrimaryMap<TableIndex, wasmparser::TableType>,
+    globals: PrimaryMap<GlobalIndex, wasmparser::GlobalType>,
     exports: HashMap<String, FuncIndex>,
+    memory_exports: HashMap<String, MemoryIndex>,
+    table_exports: HashMap<String, TableIndex>,
+    global_exports: HashMap<String, GlobalIndex>,
+    /// Whether the module imports a linear memory from the host. Unlike a
+    /// defined-but-unexported memory, an imported memory is always
+    /// reachable by the host, since the host is the one that provided it.
+    imports_memory: bool,
+    /// Function names recovered from the custom "name" section, if present.
+    /// `MissingExport` already names the witx-side function it expected;
+    /// this is used to additionally point out a same-named function that
+    /// exists in the module but isn't exported, which is otherwise
+    /// invisible since debug names aren't reachable any other way.
+    func_names: HashMap<FuncIndex, String>,
 }
 
-pub fn validate(interface: &witx::Document, module_contents: &[u8]) -> Result<(), Error> {
-    wasmparser::validate(module_contents, None)?;
+pub fn validate(
+    interface: &witx::Document,
+    module_contents: &[u8],
+    config: &ValidationConfig,
+) -> Result<(), Errors> {
+    wasmparser::validate(module_contents, Some(config.wasmparser_features()))
+        .map_err(|e| Errors(vec![Error::from(e)]))?;
 
+    let module = parse_module(module_contents).map_err(|e| Errors(vec![e]))?;
+
+    let mut errors = Vec::new();
+
+    // The host needs access to the module's linear memory so it can
+    // read/write the pointers its functions pass around. If the module
+    // imports its memory, the host already supplied it and nothing further
+    // is required; otherwise the module must export its own memory under
+    // the conventional name. witx doesn't yet have a way to annotate memory
+    // limits, so we only require the memory is reachable, not any
+    // particular size.
+    let has_memory = module.imports_memory || module.memory_exports.contains_key("memory");
+    if !has_memory {
+        errors.push(Error::MissingMemory);
+    }
+
+    // witx has no vocabulary for tables or globals, so declared table/global
+    // imports are simply permitted rather than cross-checked against the
+    // interface; they're recorded above so a future witx extension could
+    // validate them without another pass over the module.
+
+    // The wasm module validated structurally on its own; now check that it
+    // actually implements the witx interface, not just some arbitrary
+    // exports of the right name. Every mismatch is collected instead of
+    // stopping at the first one, so a caller sees the whole picture.
+    for interface_module in interface.modules() {
+        for func in interface_module.funcs() {
+            let (export_name, expected) = lower_func(&interface_module, &func);
+
+            match module.exports.get(&export_name) {
+                None => {
+                    let named = module
+                        .func_names
+                        .iter()
+                        .find(|(index, name)| {
+                            *name == func.name.as_str()
+                                && !module.exports.values().any(|e| e == *index)
+                        })
+                        .map(|(_, name)| name.clone());
+                    errors.push(match named {
+                        Some(internal_name) => {
+                            Error::MissingExportButNamed(export_name, internal_name)
+                        }
+                        None => Error::MissingExport(export_name),
+                    });
+                }
+                Some(func_index) => {
+                    let found = module.types[module.funcs[*func_index].ty].clone();
+                    if found != expected {
+                        errors.push(Error::InterfaceMismatch {
+                            func: export_name,
+                            expected,
+                            found,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Errors(errors))
+    }
+}
+
+fn parse_module(module_contents: &[u8]) -> Result<ModuleType, Error> {
     let mut module = ModuleType {
         types: PrimaryMap::new(),
         funcs: PrimaryMap::new(),
+        memories: PrimaryMap::new(),
+        tables: PrimaryMap::new(),
+        globals: PrimaryMap::new(),
         exports: HashMap::new(),
+        memory_exports: HashMap::new(),
+        table_exports: HashMap::new(),
+        global_exports: HashMap::new(),
+        imports_memory: false,
+        func_names: HashMap::new(),
     };
 
     let mut module_reader = ModuleReader::new(module_contents)?;
@@ -87,23 +228,15 @@ pub fn validate(interface: &witx::Document, module_contents: &[u8]) -> Result<()
                                 import: Some((import.module.to_owned(), import.field.to_owned())),
                             });
                         }
-                        ImportSectionEntryType::Memory(_) => {
-                            Err(Error::Unsupported(format!(
-                                "memory import {}:{}",
-                                import.module, import.field
-                            )))?;
+                        ImportSectionEntryType::Memory(memty) => {
+                            module.imports_memory = true;
+                            module.memories.push(memty);
                         }
-                        ImportSectionEntryType::Table(_) => {
-                            Err(Error::Unsupported(format!(
-                                "table import {}:{}",
-                                import.module, import.field
-                            )))?;
+                        ImportSectionEntryType::Table(tablety) => {
+                            module.tables.push(tablety);
                         }
-                        ImportSectionEntryType::Global(_) => {
-                            Err(Error::Unsupported(format!(
-                                "global import {}:{}",
-                                import.module, import.field
-                            )))?;
+                        ImportSectionEntryType::Global(globalty) => {
+                            module.globals.push(globalty);
                         }
                     }
                 }
@@ -118,7 +251,24 @@ pub fn validate(interface: &witx::Document, module_contents: &[u8]) -> Result<()
                                 FuncIndex::from_u32(export.index),
                             );
                         }
-                        _ => {} // Dont care about other exports
+                        ExternalKind::Memory => {
+                            module.memory_exports.insert(
+                                export.field.to_string(),
+                                MemoryIndex::from_u32(export.index),
+                            );
+                        }
+                        ExternalKind::Table => {
+                            module.table_exports.insert(
+                                export.field.to_string(),
+                                TableIndex::from_u32(export.index),
+                            );
+                        }
+                        ExternalKind::Global => {
+                            module.global_exports.insert(
+                                export.field.to_string(),
+                                GlobalIndex::from_u32(export.index),
+                            );
+                        }
                     }
                 }
             }
@@ -128,9 +278,37 @@ pub fn validate(interface: &witx::Document, module_contents: &[u8]) -> Result<()
                     module.funcs.push(Func { ty, import: None });
                 }
             }
+            SectionContent::Memory(memories) => {
+                for memty in memories {
+                    module.memories.push(memty?);
+                }
+            }
+            SectionContent::Table(tables) => {
+                for tablety in tables {
+                    module.tables.push(tablety?);
+                }
+            }
+            SectionContent::Global(globals) => {
+                for global in globals {
+                    module.globals.push(global?.ty);
+                }
+            }
+            SectionContent::Custom(custom) if custom.name == "name" => {
+                let mut names = NameSectionReader::new(custom.data, custom.data_offset)?;
+                while !names.eof() {
+                    if let Name::Function(mut func_names) = names.read()? {
+                        while !func_names.eof() {
+                            let naming = func_names.read()?;
+                            module
+                                .func_names
+                                .insert(FuncIndex::from_u32(naming.index), naming.name.to_string());
+                        }
+                    }
+                }
+            }
             _ => {} // Dont care about other sections
         }
     }
 
-    Ok(())
+    Ok(module)
 }
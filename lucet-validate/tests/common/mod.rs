@@ -0,0 +1,291 @@
+//! A small, deterministic, seed-driven generator of witx interfaces and
+//! matching core wasm modules, in the style of `wasm-smith`: a byte seed
+//! drives every choice, so a failing case can be reproduced just by
+//! printing the seed. Unlike `wasm-smith`, this only ever generates
+//! modules that are valid by construction (the ABI lowering is applied
+//! directly, not discovered after the fact), since the point is to exercise
+//! `lucet_validate::validate`'s conformance checking, not general wasm
+//! validity.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+/// A tiny seeded PRNG (a 64-bit linear congruential generator that yields
+/// the high bits of its state), good enough to pick among a handful of
+/// choices deterministically from an arbitrary byte seed.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: &[u8]) -> Self {
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        for &byte in seed {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(byte as u64 + 1);
+        }
+        Rng { state: state | 1 }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.state >> 33) as u32
+    }
+
+    fn gen_range(&mut self, lo: usize, hi: usize) -> usize {
+        lo + (self.next_u32() as usize) % (hi - lo)
+    }
+}
+
+/// The witx scalar types this generator knows how to synthesize, alongside
+/// how they lower to core wasm (mirrors `abi::lower_type`).
+#[derive(Clone, Copy, Debug)]
+pub enum Scalar {
+    U8,
+    U32,
+    U64,
+    F32,
+    F64,
+    String,
+}
+
+const SCALARS: &[Scalar] = &[
+    Scalar::U8,
+    Scalar::U32,
+    Scalar::U64,
+    Scalar::F32,
+    Scalar::F64,
+    Scalar::String,
+];
+
+impl Scalar {
+    fn witx_name(self) -> &'static str {
+        match self {
+            Scalar::U8 => "u8",
+            Scalar::U32 => "u32",
+            Scalar::U64 => "u64",
+            Scalar::F32 => "f32",
+            Scalar::F64 => "f64",
+            Scalar::String => "string",
+        }
+    }
+
+    /// The core wasm value type(s) this scalar lowers to.
+    fn core_types(self) -> &'static [u8] {
+        match self {
+            Scalar::U8 | Scalar::U32 => &[TY_I32],
+            Scalar::U64 => &[TY_I64],
+            Scalar::F32 => &[TY_F32],
+            Scalar::F64 => &[TY_F64],
+            Scalar::String => &[TY_I32, TY_I32],
+        }
+    }
+}
+
+const TY_I32: u8 = 0x7f;
+const TY_I64: u8 = 0x7e;
+const TY_F32: u8 = 0x7d;
+const TY_F64: u8 = 0x7c;
+
+pub struct GeneratedFunc {
+    pub name: String,
+    pub params: Vec<Scalar>,
+    pub result: Option<Scalar>,
+}
+
+pub struct GeneratedInterface {
+    pub module_name: String,
+    pub funcs: Vec<GeneratedFunc>,
+}
+
+impl GeneratedInterface {
+    /// Synthesize an interface (and, via `core_signature`, the module that
+    /// conforms to it) from a byte seed.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let mut rng = Rng::new(seed);
+        let func_count = rng.gen_range(1, 4);
+        let funcs = (0..func_count)
+            .map(|i| {
+                let param_count = rng.gen_range(0, 4);
+                let params = (0..param_count)
+                    .map(|_| SCALARS[rng.gen_range(0, SCALARS.len())])
+                    .collect();
+                let result = if rng.gen_range(0, 2) == 0 {
+                    None
+                } else {
+                    Some(SCALARS[rng.gen_range(0, SCALARS.len())])
+                };
+                GeneratedFunc {
+                    name: format!("f{}", i),
+                    params,
+                    result,
+                }
+            })
+            .collect();
+        GeneratedInterface {
+            module_name: "test".to_string(),
+            funcs,
+        }
+    }
+
+    pub fn export_name(&self, func: &GeneratedFunc) -> String {
+        format!("{}::{}", self.module_name, func.name)
+    }
+
+    /// Render this interface as witx source text.
+    pub fn witx_source(&self) -> String {
+        let mut src = format!("(module ${}\n", self.module_name);
+        for func in &self.funcs {
+            write!(src, "  (@interface func (export \"{}\")", func.name).unwrap();
+            for (i, param) in func.params.iter().enumerate() {
+                write!(src, " (param $p{} {})", i, param.witx_name()).unwrap();
+            }
+            if let Some(result) = func.result {
+                write!(src, " (result $r {})", result.witx_name()).unwrap();
+            }
+            src.push_str(")\n");
+        }
+        src.push_str(")\n");
+        src
+    }
+
+    /// Parse `witx_source` back into a `witx::Document` via a scratch file,
+    /// since the witx parser reads from paths rather than strings.
+    pub fn parse(&self) -> witx::Document {
+        let mut path = PathBuf::from(std::env::temp_dir());
+        path.push(format!(
+            "lucet-validate-fuzz-{}-{}.witx",
+            std::process::id(),
+            self.module_name
+        ));
+        fs::write(&path, self.witx_source()).expect("write scratch witx file");
+        let doc = witx::parse_witx(&[&path]).expect("parse generated witx source");
+        let _ = fs::remove_file(&path);
+        doc
+    }
+
+    /// The core wasm type section entry (params, results) each function
+    /// lowers to, following the same rules as `abi::lower_func`: a result
+    /// is only ever returned directly when it lowers to a single atom;
+    /// `string` results (two atoms: pointer, length) lower to an appended
+    /// `i32` out-param instead, exactly like `abi.rs` does for them.
+    fn core_signature(func: &GeneratedFunc) -> (Vec<u8>, Vec<u8>) {
+        let mut params = Vec::new();
+        for p in &func.params {
+            params.extend_from_slice(p.core_types());
+        }
+        let returns = match func.result.map(Scalar::core_types) {
+            Some([atom]) => vec![*atom],
+            Some(_multi_atom) => {
+                params.push(TY_I32);
+                vec![]
+            }
+            None => vec![],
+        };
+        (params, returns)
+    }
+
+    /// Build a core wasm module that exports one function per interface
+    /// function, with the exact signature `validate` expects, plus the
+    /// required `memory` export.
+    pub fn build_conforming_module(&self) -> Vec<u8> {
+        let sigs: Vec<_> = self.funcs.iter().map(Self::core_signature).collect();
+        build_module(self, &sigs)
+    }
+
+    /// Like `build_conforming_module`, but mutates the first function's
+    /// signature so it no longer matches the interface: a parameter is
+    /// dropped if there is one, otherwise an `i32` param is added.
+    pub fn build_mismatched_module(&self) -> Vec<u8> {
+        let mut sigs: Vec<_> = self.funcs.iter().map(Self::core_signature).collect();
+        let (params, _returns) = &mut sigs[0];
+        if params.pop().is_none() {
+            params.push(TY_I32);
+        }
+        build_module(self, &sigs)
+    }
+}
+
+fn leb128(mut value: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if value == 0 {
+            break;
+        }
+    }
+    out
+}
+
+fn section(id: u8, content: Vec<u8>) -> Vec<u8> {
+    let mut out = vec![id];
+    out.extend(leb128(content.len() as u32));
+    out.extend(content);
+    out
+}
+
+/// Emit a minimal wasm module with one exported function per `sigs` entry
+/// (named per `interface.export_name`) and a single exported memory. Every
+/// function body is just `unreachable`, which type-checks against any
+/// signature, so only the declared signature (not the body) is under test.
+fn build_module(interface: &GeneratedInterface, sigs: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+    let mut module = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+    let mut types = leb128(sigs.len() as u32);
+    for (params, returns) in sigs {
+        types.push(0x60);
+        types.extend(leb128(params.len() as u32));
+        types.extend_from_slice(params);
+        types.extend(leb128(returns.len() as u32));
+        types.extend_from_slice(returns);
+    }
+    module.extend(section(1, types));
+
+    let mut functions = leb128(sigs.len() as u32);
+    for i in 0..sigs.len() {
+        functions.extend(leb128(i as u32));
+    }
+    module.extend(section(3, functions));
+
+    // One page of memory is enough; this crate only checks the export
+    // exists, not its size.
+    let memory = {
+        let mut m = leb128(1);
+        m.push(0x00);
+        m.extend(leb128(1));
+        m
+    };
+    module.extend(section(5, memory));
+
+    let mut exports = leb128((sigs.len() + 1) as u32);
+    for (i, func) in interface.funcs.iter().enumerate() {
+        let name = interface.export_name(func);
+        exports.extend(leb128(name.len() as u32));
+        exports.extend_from_slice(name.as_bytes());
+        exports.push(0x00); // function export
+        exports.extend(leb128(i as u32));
+    }
+    exports.extend(leb128(6));
+    exports.extend_from_slice(b"memory");
+    exports.push(0x02); // memory export
+    exports.extend(leb128(0));
+    module.extend(section(7, exports));
+
+    let mut code = leb128(sigs.len() as u32);
+    for _ in sigs {
+        let body = vec![0x00 /* locals count */, 0x00 /* unreachable */, 0x0b /* end */];
+        code.extend(leb128(body.len() as u32));
+        code.extend(body);
+    }
+    module.extend(section(10, code));
+
+    module
+}
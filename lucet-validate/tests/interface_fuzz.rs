@@ -0,0 +1,40 @@
+//! Fuzzes `validate` over synthesized witx interface / wasm module pairs:
+//! for each seed, a conforming module must be accepted, and a module whose
+//! first export's signature has been mutated must be rejected with
+//! `Error::InterfaceMismatch`.
+
+mod common;
+
+use common::GeneratedInterface;
+use lucet_validate::{validate, Error, ValidationConfig};
+
+fn check_seed(seed: &[u8]) {
+    let interface = GeneratedInterface::from_seed(seed);
+    let doc = interface.parse();
+    let config = ValidationConfig::default();
+
+    let conforming = interface.build_conforming_module();
+    validate(&doc, &conforming, &config)
+        .unwrap_or_else(|e| panic!("seed {:?}: expected conforming module to validate: {}", seed, e));
+
+    let mismatched = interface.build_mismatched_module();
+    let errors = validate(&doc, &mismatched, &config)
+        .err()
+        .unwrap_or_else(|| panic!("seed {:?}: expected mutated module to be rejected", seed));
+    assert!(
+        errors
+            .0
+            .iter()
+            .any(|e| matches!(e, Error::InterfaceMismatch { .. })),
+        "seed {:?}: expected an InterfaceMismatch, got {}",
+        seed,
+        errors
+    );
+}
+
+#[test]
+fn fuzz_interface_conformance() {
+    for seed in 0u32..256 {
+        check_seed(&seed.to_le_bytes());
+    }
+}